@@ -2,44 +2,239 @@ use crate::{
     error::{ApiError, ApiResult},
     models::{Counters, Entry, LanguageCode, Topic},
 };
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use csv::StringRecord;
+use futures::{StreamExt, TryStreamExt};
+use redis::AsyncCommands;
 use serde::{Deserialize, Deserializer};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use strum::IntoEnumIterator;
 use tokio::sync::RwLock;
+use tokio_util::io::StreamReader;
 
 pub type DictionaryHandle = RwLock<Dictionary>;
 
-#[derive(Debug)]
 pub struct Dictionary {
     url: String,
+    embedder: Box<dyn Embedder>,
+    /// Optional persistence/caching layer so a cold start can hydrate
+    /// without hitting `url`, and so several API replicas can share one
+    /// cached copy. `None` behaves exactly like before this existed.
+    store: Option<Box<dyn Store>>,
     pub entries: HashMap<u32, Entry>,
+    /// Normalized embedding vectors, keyed by [`Entry::id`], used for the
+    /// semantic ranking half of [`Dictionary::search`].
+    embeddings: HashMap<u32, Vec<f32>>,
     pub counters: Counters,
     pub last_update: DateTime<Utc>,
+    /// Rolling, time-decayed counts of what's been searched, surfaced via
+    /// [`Dictionary::trending`].
+    analytics: SearchAnalytics,
+    /// Content hash of each entry's glosses as of the last refresh, used to
+    /// detect modifications on the next one.
+    entry_hashes: HashMap<u32, u64>,
+    /// When each currently present entry was last added or modified (or, for
+    /// an entry hydrated from the store, loaded), used to answer
+    /// [`Dictionary::last_modified`].
+    entry_last_modified: HashMap<u32, DateTime<Utc>>,
+    /// A bounded log of recent add/modify/remove events, newest at the back.
+    change_log: VecDeque<EntryChange>,
+}
+
+impl std::fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("url", &self.url)
+            .field("store", &self.store.is_some())
+            .field("entries", &self.entries)
+            .field("embeddings", &self.embeddings.len())
+            .field("counters", &self.counters)
+            .field("last_update", &self.last_update)
+            .field("analytics", &self.analytics)
+            .field("change_log", &self.change_log.len())
+            .finish()
+    }
 }
 
 impl Dictionary {
-    pub async fn from_url(url: &str) -> ApiResult<DictionaryHandle> {
+    pub async fn from_url(
+        url: &str,
+        embedder: Box<dyn Embedder>,
+        store: Option<Box<dyn Store>>,
+        trending_half_life: chrono::Duration,
+    ) -> ApiResult<DictionaryHandle> {
         let mut dict = Dictionary {
             url: url.to_string(),
+            embedder,
+            store,
             entries: HashMap::new(),
+            embeddings: HashMap::new(),
             counters: Default::default(),
             last_update: Utc::now(),
+            analytics: SearchAnalytics::new(trending_half_life),
+            entry_hashes: HashMap::new(),
+            entry_last_modified: HashMap::new(),
+            change_log: VecDeque::new(),
         };
-        dict.update().await?;
+
+        if !dict.hydrate_from_store().await? {
+            dict.update().await?;
+        }
+
         Ok(RwLock::new(dict))
     }
 
+    /// Tries to populate the dictionary from its [`Store`], returning
+    /// whether a cached copy was found. A miss, a store error, or no store
+    /// configured all leave the dictionary untouched and return `Ok(false)`,
+    /// so the caller always falls back to [`fetch_dict`] — a down Redis
+    /// should degrade hydration, not block startup, mirroring how
+    /// `update`'s store write is best-effort.
+    async fn hydrate_from_store(&mut self) -> ApiResult<bool> {
+        let Some(store) = &self.store else {
+            return Ok(false);
+        };
+
+        let loaded = match (store.load_entries().await, store.load_counters().await) {
+            (Ok(entries), Ok(counters)) => (entries, counters),
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("failed to hydrate dictionary from store: {err}");
+                (None, None)
+            }
+        };
+
+        let (Some(entries), Some(counters)) = loaded else {
+            return Ok(false);
+        };
+
+        let glosses = compute_glosses(&entries);
+        self.embeddings = embed_entries(
+            &glosses,
+            &self.entry_hashes,
+            &self.embeddings,
+            self.embedder.as_ref(),
+        )
+        .await?;
+        self.seed_entry_state(&glosses);
+        self.entries = entries;
+        self.counters = counters;
+        self.last_update = Utc::now();
+        Ok(true)
+    }
+
+    /// Populates `entry_hashes` and `entry_last_modified` from a cached copy
+    /// without touching the change log, since a cache hit reflects state this
+    /// process hasn't seen before, not a new change to report via
+    /// `changes_since`. `entry_last_modified` still needs seeding so
+    /// `last_modified` can report hydrated entries as present, per its
+    /// contract, instead of `None` until the next real refresh touches them.
+    fn seed_entry_state(&mut self, glosses: &HashMap<u32, String>) {
+        let now = Utc::now();
+        self.entry_hashes = glosses
+            .iter()
+            .map(|(id, gloss)| (*id, content_hash(gloss)))
+            .collect();
+        self.entry_last_modified = glosses.keys().map(|id| (*id, now)).collect();
+    }
+
     pub async fn update(&mut self) -> ApiResult<()> {
         let (entries, counters) = fetch_dict(&self.url).await?;
+        let glosses = compute_glosses(&entries);
+        self.embeddings = embed_entries(
+            &glosses,
+            &self.entry_hashes,
+            &self.embeddings,
+            self.embedder.as_ref(),
+        )
+        .await?;
+
+        if let Some(store) = &self.store {
+            // Best-effort: a flaky/down cache shouldn't discard a
+            // successful upstream fetch, it should just degrade to
+            // pre-Store behavior (no shared cache this round).
+            if let Err(err) = store.save(&entries, &counters).await {
+                eprintln!("failed to persist dictionary to store: {err}");
+            }
+        }
+
+        self.record_changes(&entries, &glosses);
         self.entries = entries;
         self.counters = counters;
         self.last_update = Utc::now();
         Ok(())
     }
 
+    /// Diffs `fresh` against the current entries by content hash, classifying
+    /// each id as added, modified, or removed, and appends the result to the
+    /// bounded [`Dictionary::change_log`].
+    fn record_changes(&mut self, fresh: &HashMap<u32, Entry>, glosses: &HashMap<u32, String>) {
+        let now = Utc::now();
+
+        for (id, entry) in fresh {
+            let new_hash = glosses
+                .get(id)
+                .map(|gloss| content_hash(gloss))
+                .unwrap_or_default();
+            let kind = match self.entry_hashes.insert(*id, new_hash) {
+                None => Some(ChangeKind::Added),
+                Some(old_hash) if old_hash != new_hash => Some(ChangeKind::Modified),
+                Some(_) => None,
+            };
+
+            if let Some(kind) = kind {
+                self.entry_last_modified.insert(*id, now);
+                self.push_change(EntryChange {
+                    id: *id,
+                    kind,
+                    entry: Some(entry.clone()),
+                    at: now,
+                });
+            }
+        }
+
+        let removed_ids: Vec<u32> = self
+            .entry_hashes
+            .keys()
+            .filter(|id| !fresh.contains_key(id))
+            .copied()
+            .collect();
+
+        for id in removed_ids {
+            self.entry_hashes.remove(&id);
+            self.entry_last_modified.remove(&id);
+            self.push_change(EntryChange {
+                id,
+                kind: ChangeKind::Removed,
+                entry: None,
+                at: now,
+            });
+        }
+    }
+
+    fn push_change(&mut self, change: EntryChange) {
+        self.change_log.push_back(change);
+        while self.change_log.len() > MAX_CHANGE_LOG_LEN {
+            self.change_log.pop_front();
+        }
+    }
+
+    /// Entries added, modified, or removed since `since`, letting clients
+    /// (mobile apps, caches) sync deltas instead of re-pulling the whole
+    /// dictionary after every refresh.
+    pub fn changes_since(&self, since: DateTime<Utc>) -> Vec<EntryChange> {
+        self.change_log
+            .iter()
+            .filter(|change| change.at > since)
+            .cloned()
+            .collect()
+    }
+
+    /// When `id` was last added or modified, if it's currently present.
+    pub fn last_modified(&self, id: u32) -> Option<DateTime<Utc>> {
+        self.entry_last_modified.get(&id).copied()
+    }
+
     pub fn get_entry(&self, id: u32) -> ApiResult<Entry> {
         self.entries
             .get(&id)
@@ -47,20 +242,40 @@ impl Dictionary {
             .cloned()
     }
 
-    pub fn search(
+    /// Searches the dictionary, blending keyword relevance with semantic
+    /// similarity.
+    ///
+    /// `alpha` controls the blend: `0.0` is today's pure keyword search,
+    /// `1.0` is pure semantic search, and values in between linearly
+    /// interpolate `final = alpha * semantic_score + (1 - alpha) *
+    /// keyword_score`. The `topics`/`sem_id` filters, and with query text the
+    /// `text_langs` filter (an entry needs a gloss in one of those languages),
+    /// are always applied first as hard pre-filters, so both scores are only
+    /// ever computed over the candidate set they leave behind.
+    pub async fn search(
         &self,
         text: Option<String>,
         text_langs: Vec<LanguageCode>,
         sem_id: Option<u32>,
         topics: Vec<Topic>,
-    ) -> ApiResult<Vec<Entry>> {
-        let langs = if text_langs.is_empty() {
+        alpha: f32,
+    ) -> ApiResult<Vec<ScoredEntry>> {
+        // Recorded against the caller's original filter, not `langs` below:
+        // a search with no language filter means "searched everywhere", not
+        // "searched in all 14 languages at once", and counting it against
+        // every language would drown out genuinely language-scoped queries
+        // in `trending`.
+        if let Some(t) = &text {
+            self.analytics.record(t, &text_langs).await;
+        }
+
+        let langs: Vec<LanguageCode> = if text_langs.is_empty() {
             LanguageCode::iter().collect()
         } else {
             text_langs
         };
 
-        let filter = |e: &&Entry| -> bool {
+        let pre_filter = |e: &&Entry| -> bool {
             let sem_filter = match (sem_id, e.sem_id) {
                 (Some(a), Some(b)) => a == b,
                 (Some(_), None) => false,
@@ -73,38 +288,648 @@ impl Dictionary {
                 e.topic.map_or(false, |t| topics.contains(&t))
             };
 
-            let text_filter = match &text {
-                Some(t) => e.matches(t, &langs),
-                None => true,
+            let lang_filter =
+                text.is_none() || langs.iter().any(|lang| entry_field(e, *lang).is_some());
+
+            sem_filter && topic_filter && lang_filter
+        };
+
+        let query_embedding = match (&text, alpha > 0.0) {
+            (Some(t), true) => Some(normalize(self.embedder.embed(t).await?)),
+            _ => None,
+        };
+
+        let candidates = self.entries.values().filter(pre_filter);
+
+        let mut scored: Vec<ScoredEntry> = match &text {
+            Some(t) => {
+                let query_words = tokenize(t);
+                candidates
+                    .filter_map(|e| {
+                        let keyword_score = score_entry(e, &query_words, &langs);
+                        let semantic_score = query_embedding.as_ref().and_then(|query_vector| {
+                            self.embeddings
+                                .get(&e.id)
+                                .map(|entry_vector| cosine_similarity(query_vector, entry_vector))
+                        });
+
+                        if keyword_score.is_none() && semantic_score.is_none() {
+                            return None;
+                        }
+
+                        let keyword_norm =
+                            keyword_score.map(normalize_keyword_score).unwrap_or(0.0);
+                        let semantic_norm = semantic_score.map(|s| (s + 1.0) / 2.0).unwrap_or(0.0);
+                        let score = alpha * semantic_norm + (1.0 - alpha) * keyword_norm;
+
+                        Some(ScoredEntry {
+                            entry: e.clone(),
+                            score,
+                        })
+                    })
+                    .collect()
+            }
+            None => candidates
+                .map(|e| ScoredEntry {
+                    entry: e.clone(),
+                    score: 0.0,
+                })
+                .collect(),
+        };
+
+        scored.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Ok(scored)
+    }
+
+    /// The top `limit` most-searched terms for `lang`, decayed so stale
+    /// terms fall out. Complements `counters`, which only report static
+    /// corpus sizes, with a live usage signal.
+    pub async fn trending(&self, lang: LanguageCode, limit: usize) -> Vec<(String, f32)> {
+        self.analytics.trending(lang, limit).await
+    }
+}
+
+/// Produces an embedding vector for a piece of text, so [`Dictionary`] can
+/// rank search results by semantic similarity rather than keyword overlap
+/// alone. Implementations don't need to normalize their output; callers
+/// normalize before comparing vectors.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    async fn embed(&self, text: &str) -> ApiResult<Vec<f32>>;
+}
+
+/// Calls a remote embedding endpoint (an HTTP service returning a JSON
+/// `{"embedding": [...]}` body) for each text.
+pub struct RemoteEmbedder {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl RemoteEmbedder {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RemoteEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+#[async_trait]
+impl Embedder for RemoteEmbedder {
+    async fn embed(&self, text: &str) -> ApiResult<Vec<f32>> {
+        let response: RemoteEmbeddingResponse = self
+            .client
+            .post(&self.endpoint)
+            .json(&serde_json::json!({ "input": text }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        Ok(response.embedding)
+    }
+}
+
+/// Wraps an in-process embedding model so no network round trip is needed.
+/// The model itself is injected, since loading weights is out of scope here.
+pub trait LocalModel: Send + Sync {
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+pub struct LocalEmbedder {
+    model: Box<dyn LocalModel>,
+}
+
+impl LocalEmbedder {
+    pub fn new(model: Box<dyn LocalModel>) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    async fn embed(&self, text: &str) -> ApiResult<Vec<f32>> {
+        Ok(self.model.embed(text))
+    }
+}
+
+/// How many glosses to embed concurrently, bounding how many outstanding
+/// embedder calls (HTTP round trips, for [`RemoteEmbedder`]) are in flight
+/// at once.
+const EMBED_CONCURRENCY: usize = 16;
+
+/// Embeds every entry's precomputed gloss, normalizing each vector so later
+/// cosine-similarity comparisons reduce to a dot product. An entry whose
+/// gloss hash matches `previous_hashes` reuses its `previous_embeddings`
+/// vector instead of calling `embedder` again, since a routine refresh
+/// usually changes only a handful of entries. Entries that do need a fresh
+/// embedding are embedded concurrently rather than one at a time.
+async fn embed_entries(
+    glosses: &HashMap<u32, String>,
+    previous_hashes: &HashMap<u32, u64>,
+    previous_embeddings: &HashMap<u32, Vec<f32>>,
+    embedder: &dyn Embedder,
+) -> ApiResult<HashMap<u32, Vec<f32>>> {
+    let mut embeddings = HashMap::with_capacity(glosses.len());
+    let mut to_embed = Vec::new();
+
+    for (id, gloss) in glosses {
+        let reused = previous_hashes
+            .get(id)
+            .filter(|&&prev_hash| prev_hash == content_hash(gloss))
+            .and_then(|_| previous_embeddings.get(id).cloned());
+
+        match reused {
+            Some(vector) => {
+                embeddings.insert(*id, vector);
+            }
+            None => to_embed.push((*id, gloss.as_str())),
+        }
+    }
+
+    let fresh: Vec<(u32, ApiResult<Vec<f32>>)> = futures::stream::iter(to_embed)
+        .map(|(id, gloss)| async move { (id, embedder.embed(gloss).await) })
+        .buffer_unordered(EMBED_CONCURRENCY)
+        .collect()
+        .await;
+
+    for (id, result) in fresh {
+        embeddings.insert(id, normalize(result?));
+    }
+
+    Ok(embeddings)
+}
+
+/// All of an entry's glosses across every language, concatenated for
+/// embedding and content-hashing.
+fn entry_gloss_text(entry: &Entry) -> String {
+    LanguageCode::iter()
+        .filter_map(|lang| entry_field(entry, lang))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Computes each entry's gloss once per refresh, so embedding and
+/// content-hashing don't each rebuild it from scratch.
+fn compute_glosses(entries: &HashMap<u32, Entry>) -> HashMap<u32, String> {
+    entries
+        .iter()
+        .map(|(id, entry)| (*id, entry_gloss_text(entry)))
+        .collect()
+}
+
+fn normalize(vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        vector
+    } else {
+        vector.into_iter().map(|v| v / norm).collect()
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The raw score at which [`normalize_keyword_score`] reaches 0.5. Unlike a
+/// hard cap, this never clamps two different raw scores to the same
+/// normalized value, so results stay distinguishable no matter how high
+/// `score_entry` can climb.
+const KEYWORD_SCORE_HALF_SATURATION: f32 = 4.0;
+
+fn normalize_keyword_score(score: f32) -> f32 {
+    score / (score + KEYWORD_SCORE_HALF_SATURATION)
+}
+
+/// Persists parsed dictionary data so a cold start can hydrate without
+/// re-fetching `url`, and so several API replicas can share one cache.
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn load_entries(&self) -> ApiResult<Option<HashMap<u32, Entry>>>;
+    async fn load_counters(&self) -> ApiResult<Option<Counters>>;
+    async fn save(&self, entries: &HashMap<u32, Entry>, counters: &Counters) -> ApiResult<()>;
+}
+
+/// A [`Store`] backed by Redis, namespacing its keys so multiple
+/// dictionaries (or deployments) can share one Redis instance, and expiring
+/// them after `ttl_seconds` so a stale cache eventually falls back to a
+/// real fetch.
+pub struct RedisStore {
+    client: redis::Client,
+    namespace: String,
+    ttl_seconds: usize,
+}
+
+impl RedisStore {
+    pub fn new(
+        redis_url: &str,
+        namespace: impl Into<String>,
+        ttl_seconds: usize,
+    ) -> ApiResult<Self> {
+        Ok(Self {
+            client: redis::Client::open(redis_url)?,
+            namespace: namespace.into(),
+            ttl_seconds,
+        })
+    }
+
+    fn entries_key(&self) -> String {
+        format!("{}:entries", self.namespace)
+    }
+
+    fn counters_key(&self) -> String {
+        format!("{}:counters", self.namespace)
+    }
+}
+
+#[async_trait]
+impl Store for RedisStore {
+    async fn load_entries(&self) -> ApiResult<Option<HashMap<u32, Entry>>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let raw: Option<String> = conn.get(self.entries_key()).await?;
+        Ok(raw.and_then(|s| serde_json::from_str(&s).ok()))
+    }
+
+    async fn load_counters(&self) -> ApiResult<Option<Counters>> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let raw: Option<String> = conn.get(self.counters_key()).await?;
+        Ok(raw.and_then(|s| serde_json::from_str(&s).ok()))
+    }
+
+    async fn save(&self, entries: &HashMap<u32, Entry>, counters: &Counters) -> ApiResult<()> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let entries_json = serde_json::to_string(entries)?;
+        let counters_json = serde_json::to_string(counters)?;
+        conn.set_ex::<_, _, ()>(self.entries_key(), entries_json, self.ttl_seconds as u64)
+            .await?;
+        conn.set_ex::<_, _, ()>(self.counters_key(), counters_json, self.ttl_seconds as u64)
+            .await?;
+        Ok(())
+    }
+}
+
+/// How often a term has been searched, decayed by how long ago it was last
+/// seen rather than on a fixed schedule, so [`SearchAnalytics`] doesn't need
+/// a background task to keep counts fresh.
+#[derive(Debug, Clone)]
+struct TermCount {
+    count: f32,
+    last_seen: DateTime<Utc>,
+}
+
+/// Where a recorded search query counts toward trending terms: a specific
+/// language, or [`AnalyticsScope::Unscoped`] for a query that wasn't
+/// filtered to any particular language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AnalyticsScope {
+    Lang(LanguageCode),
+    Unscoped,
+}
+
+/// Caps how many distinct terms [`SearchAnalytics`] tracks per scope, since
+/// terms come from arbitrary free-text queries rather than a bounded set of
+/// ids; the weakest (most decayed) term is evicted once a scope is over the
+/// cap, mirroring [`MAX_CHANGE_LOG_LEN`].
+const MAX_TERMS_PER_SCOPE: usize = 1_000;
+
+/// Tracks search-query popularity per language with time decay (counts
+/// halve every `half_life`), so [`Dictionary::trending`] reflects what's
+/// currently being searched rather than what was searched once, long ago.
+#[derive(Debug)]
+struct SearchAnalytics {
+    half_life: chrono::Duration,
+    counts: RwLock<HashMap<AnalyticsScope, HashMap<String, TermCount>>>,
+}
+
+impl SearchAnalytics {
+    fn new(half_life: chrono::Duration) -> Self {
+        Self {
+            half_life,
+            counts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Records a search query against `langs`, tokenized the same way
+    /// [`Dictionary::search`] tokenizes query text. An empty `langs` records
+    /// against [`AnalyticsScope::Unscoped`] rather than every language, since
+    /// an unfiltered query isn't evidence about any one of them.
+    async fn record(&self, text: &str, langs: &[LanguageCode]) {
+        let now = Utc::now();
+        let terms = tokenize(text);
+        if terms.is_empty() {
+            return;
+        }
+
+        let scopes: Vec<AnalyticsScope> = if langs.is_empty() {
+            vec![AnalyticsScope::Unscoped]
+        } else {
+            langs.iter().copied().map(AnalyticsScope::Lang).collect()
+        };
+
+        let mut counts = self.counts.write().await;
+        for scope in scopes {
+            let per_scope = counts.entry(scope).or_default();
+            for term in &terms {
+                let decayed = per_scope
+                    .get(term)
+                    .map(|tc| self.decay(tc.count, tc.last_seen, now))
+                    .unwrap_or(0.0);
+                per_scope.insert(
+                    term.clone(),
+                    TermCount {
+                        count: decayed + 1.0,
+                        last_seen: now,
+                    },
+                );
+
+                if per_scope.len() > MAX_TERMS_PER_SCOPE {
+                    self.evict_weakest(per_scope, now);
+                }
+            }
+        }
+    }
+
+    /// Removes whichever term has the lowest decayed count, to make room
+    /// under [`MAX_TERMS_PER_SCOPE`].
+    fn evict_weakest(&self, per_scope: &mut HashMap<String, TermCount>, now: DateTime<Utc>) {
+        if let Some(weakest) = per_scope
+            .iter()
+            .min_by(|a, b| {
+                self.decay(a.1.count, a.1.last_seen, now)
+                    .partial_cmp(&self.decay(b.1.count, b.1.last_seen, now))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(term, _)| term.clone())
+        {
+            per_scope.remove(&weakest);
+        }
+    }
+
+    /// The top `limit` terms searched for `lang`, highest decayed count
+    /// first.
+    async fn trending(&self, lang: LanguageCode, limit: usize) -> Vec<(String, f32)> {
+        let now = Utc::now();
+        let counts = self.counts.read().await;
+        let Some(per_scope) = counts.get(&AnalyticsScope::Lang(lang)) else {
+            return Vec::new();
+        };
+
+        let mut terms: Vec<(String, f32)> = per_scope
+            .iter()
+            .map(|(term, tc)| (term.clone(), self.decay(tc.count, tc.last_seen, now)))
+            .collect();
+
+        terms.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        terms.truncate(limit);
+        terms
+    }
+
+    /// Exponentially decays `count`, halving it every `half_life` that has
+    /// elapsed since `last_seen`.
+    fn decay(&self, count: f32, last_seen: DateTime<Utc>, now: DateTime<Utc>) -> f32 {
+        let elapsed_secs = (now - last_seen).num_seconds().max(0) as f32;
+        let half_life_secs = self.half_life.num_seconds().max(1) as f32;
+        count * 0.5f32.powf(elapsed_secs / half_life_secs)
+    }
+}
+
+/// An [`Entry`] paired with its relevance score from [`Dictionary::search`].
+///
+/// Higher scores rank first; a score of `0.0` means the search had no query
+/// text to rank against.
+#[derive(Debug, Clone)]
+pub struct ScoredEntry {
+    pub entry: Entry,
+    pub score: f32,
+}
+
+/// How an entry differs from the previous refresh, as recorded in
+/// [`Dictionary::change_log`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// One entry's change as of a given refresh, returned by
+/// [`Dictionary::changes_since`]. `entry` is `None` for [`ChangeKind::Removed`].
+#[derive(Debug, Clone)]
+pub struct EntryChange {
+    pub id: u32,
+    pub kind: ChangeKind,
+    pub entry: Option<Entry>,
+    pub at: DateTime<Utc>,
+}
+
+/// Caps [`Dictionary::change_log`] so it can't grow unbounded across many
+/// refreshes; only the most recent changes are kept.
+const MAX_CHANGE_LOG_LEN: usize = 10_000;
+
+/// Hashes an entry's gloss fields, so a refresh can tell whether an entry
+/// actually changed without a full field-by-field diff.
+fn content_hash(gloss: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    gloss.hash(&mut hasher);
+    hasher.finish()
+}
+
+const MAX_TYPO_DISTANCE_SHORT: usize = 4;
+const MAX_TYPO_DISTANCE_MEDIUM: usize = 8;
+
+/// Splits free text into lowercase, whitespace-separated words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// The typo budget for a word of a given length: exact matches for short
+/// words, one edit for medium words, two edits for longer ones.
+fn typo_budget(word: &str) -> usize {
+    match word.chars().count() {
+        0..=MAX_TYPO_DISTANCE_SHORT => 0,
+        5..=MAX_TYPO_DISTANCE_MEDIUM => 1,
+        _ => 2,
+    }
+}
+
+/// Classic Levenshtein edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (n, m) = (a.len(), b.len());
+
+    let mut row: Vec<usize> = (0..=m).collect();
+    for i in 1..=n {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=m {
+            let cur = row[j];
+            row[j] = if a[i - 1] == b[j - 1] {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j - 1])
             };
+            prev = cur;
+        }
+    }
+    row[m]
+}
+
+/// The best match of `query_word` against `field_word`: `Some(typo_cost)`
+/// (0 = exact, 1/2 = within the typo budget) plus whether it was a prefix
+/// match, or `None` if it doesn't match at all.
+fn word_match(query_word: &str, field_word: &str) -> Option<(usize, bool)> {
+    if field_word.starts_with(query_word) {
+        return Some((0, query_word.len() != field_word.len()));
+    }
 
-            sem_filter && topic_filter && text_filter
+    let budget = typo_budget(query_word);
+    let distance = levenshtein(query_word, field_word);
+    if distance <= budget {
+        Some((distance, false))
+    } else {
+        None
+    }
+}
+
+/// Looks up the gloss field on `entry` for a given language, mirroring the
+/// column layout used by [`RawEntry`] and [`Counters`].
+fn entry_field(entry: &Entry, lang: LanguageCode) -> Option<&str> {
+    let field = match lang {
+        LanguageCode::Lat => &entry.lat,
+        LanguageCode::Iro => &entry.iro,
+        LanguageCode::Por => &entry.por,
+        LanguageCode::Spa => &entry.spa,
+        LanguageCode::Cat => &entry.cat,
+        LanguageCode::Occ => &entry.occ,
+        LanguageCode::Fra => &entry.fra,
+        LanguageCode::Srd => &entry.srd,
+        LanguageCode::Ita => &entry.ita,
+        LanguageCode::Rom => &entry.rom,
+        LanguageCode::Eng => &entry.eng,
+        LanguageCode::Fol => &entry.fol,
+        LanguageCode::Frk => &entry.frk,
+        LanguageCode::Sla => &entry.sla,
+    };
+    field.as_deref()
+}
+
+/// Scores `entry` against `query_words`, or returns `None` if no query word
+/// matches any field in `langs` at all (i.e. it wouldn't have passed the old
+/// boolean filter).
+fn score_entry(entry: &Entry, query_words: &[String], langs: &[LanguageCode]) -> Option<f32> {
+    if query_words.is_empty() {
+        return Some(0.0);
+    }
+
+    let fields: Vec<Vec<String>> = langs
+        .iter()
+        .filter_map(|lang| entry_field(entry, *lang))
+        .map(tokenize)
+        .collect();
+
+    if fields.is_empty() {
+        return None;
+    }
+
+    let mut matched_words = 0usize;
+    let mut typo_score = 0.0f32;
+    let mut proximity_score = 0.0f32;
+    let mut exactness_bonus = 0.0f32;
+
+    for query_word in query_words {
+        // (cost, is_prefix, word_index) — word_index is where in the
+        // matched field's token list the match landed, so proximity
+        // rewards matches near the start of the matched text itself,
+        // not whichever language happens to be listed first in `langs`.
+        let mut best: Option<(usize, bool, usize)> = None;
+
+        for words in &fields {
+            for (word_index, field_word) in words.iter().enumerate() {
+                if let Some((cost, is_prefix)) = word_match(query_word, field_word) {
+                    let candidate = (cost, is_prefix, word_index);
+                    best = Some(match best {
+                        Some(b) if (b.0, b.2) <= (candidate.0, candidate.2) => b,
+                        _ => candidate,
+                    });
+                }
+            }
+        }
+
+        let Some((cost, is_prefix, word_index)) = best else {
+            continue;
         };
 
-        Ok(self
-            .entries
-            .values()
-            .filter(filter)
-            .cloned()
-            .collect::<Vec<_>>())
+        matched_words += 1;
+        typo_score += match cost {
+            0 => 1.0,
+            1 => 0.6,
+            _ => 0.3,
+        };
+        if is_prefix {
+            typo_score += 0.15;
+        }
+        proximity_score += 1.0 / (1.0 + word_index as f32);
     }
+
+    if matched_words == 0 {
+        return None;
+    }
+
+    let query_text = query_words.join(" ");
+    if fields.iter().any(|words| words.join(" ") == query_text) {
+        exactness_bonus = 2.0;
+    }
+
+    let flag_bonus = if entry.essential_flag {
+        0.3
+    } else if entry.basic_flag {
+        0.15
+    } else {
+        0.0
+    };
+
+    let coverage = matched_words as f32 / query_words.len() as f32;
+
+    Some(coverage * 3.0 + typo_score + proximity_score + exactness_bonus + flag_bonus)
 }
 
+/// Streams the dictionary CSV from `url` and deserializes it incrementally,
+/// so peak memory stays near one record instead of the full response body
+/// and the counters row is available as soon as it's read.
 async fn fetch_dict(url: &str) -> ApiResult<(HashMap<u32, Entry>, Counters)> {
-    let response = reqwest::get(url).await?.text().await?;
-    let mut reader = csv::ReaderBuilder::new()
+    let byte_stream = reqwest::get(url)
+        .await?
+        .bytes_stream()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+    let body_reader = StreamReader::new(byte_stream);
+
+    let mut csv_reader = csv_async::AsyncReaderBuilder::new()
         .has_headers(false)
-        .from_reader(response.as_bytes());
+        .create_reader(body_reader);
 
     let mut entries = HashMap::new();
 
-    let mut records = reader.records();
-    let _header = records.next();
+    let mut records = csv_reader.records();
+    let _header = records.next().await;
 
     println!("Reading counters");
-    let r_counters = records.next().ok_or(ApiError::MissingDictHeaders)??;
+    let r_counters = records.next().await.ok_or(ApiError::MissingDictHeaders)??;
 
-    fn read_int(r: &StringRecord, index: usize) -> Result<u32, ApiError> {
+    fn read_int(r: &csv_async::StringRecord, index: usize) -> Result<u32, ApiError> {
         Ok(r.get(index)
             .ok_or(ApiError::MissingDictHeaders)?
             .replace('.', "")
@@ -131,12 +956,24 @@ async fn fetch_dict(url: &str) -> ApiResult<(HashMap<u32, Entry>, Counters)> {
     };
     println!("{:?}", counters);
 
-    let _ = records.next();
+    let _ = records.next().await;
 
     println!("Reading entries");
 
-    for r in records.flatten() {
-        if let Ok(r) = r.deserialize::<RawEntry>(None) {
+    while let Some(r) = records.next().await {
+        // A malformed row (bad UTF-8, wrong field count, ...) is skipped,
+        // the same as before this loop was touched; but an IO error means
+        // the connection itself dropped mid-stream, which must propagate
+        // instead of silently truncating `entries` and reporting success.
+        let record = match r {
+            Ok(record) => record,
+            Err(err) if matches!(err.kind(), csv_async::ErrorKind::Io(_)) => {
+                return Err(err.into());
+            }
+            Err(_) => continue,
+        };
+
+        if let Ok(r) = record.deserialize::<RawEntry>(None) {
             if let Ok(r) = Entry::try_from(r) {
                 entries.insert(r.id, r);
             }
@@ -210,3 +1047,223 @@ impl TryFrom<RawEntry> for Entry {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32) -> Entry {
+        Entry {
+            id,
+            sem_id: None,
+            topic: None,
+            essential_flag: false,
+            basic_flag: false,
+            lat: None,
+            iro: None,
+            por: None,
+            spa: None,
+            cat: None,
+            occ: None,
+            fra: None,
+            srd: None,
+            ita: None,
+            rom: None,
+            eng: None,
+            fol: None,
+            frk: None,
+            sla: None,
+        }
+    }
+
+    struct StubEmbedder;
+
+    #[async_trait]
+    impl Embedder for StubEmbedder {
+        async fn embed(&self, _text: &str) -> ApiResult<Vec<f32>> {
+            Ok(vec![0.0])
+        }
+    }
+
+    fn test_dictionary() -> Dictionary {
+        Dictionary {
+            url: String::new(),
+            embedder: Box::new(StubEmbedder),
+            store: None,
+            entries: HashMap::new(),
+            embeddings: HashMap::new(),
+            counters: Default::default(),
+            last_update: Utc::now(),
+            analytics: SearchAnalytics::new(chrono::Duration::hours(1)),
+            entry_hashes: HashMap::new(),
+            entry_last_modified: HashMap::new(),
+            change_log: VecDeque::new(),
+        }
+    }
+
+    #[test]
+    fn levenshtein_counts_single_edits() {
+        assert_eq!(levenshtein("casa", "casa"), 0);
+        assert_eq!(levenshtein("casa", "caza"), 1);
+        assert_eq!(levenshtein("casa", "casas"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn typo_budget_scales_with_word_length() {
+        assert_eq!(typo_budget("sol"), 0);
+        assert_eq!(typo_budget("casa"), 0);
+        assert_eq!(typo_budget("casetta"), 1);
+        assert_eq!(typo_budget("internationale"), 2);
+    }
+
+    #[test]
+    fn word_match_finds_exact_prefix_and_typo_matches() {
+        assert_eq!(word_match("casa", "casa"), Some((0, false)));
+        assert_eq!(word_match("cas", "casa"), Some((0, true)));
+        assert_eq!(word_match("grandi", "grande"), Some((1, false)));
+        assert_eq!(word_match("xyz", "casa"), None);
+    }
+
+    #[test]
+    fn score_entry_ranks_exact_match_above_typo_match() {
+        let mut exact = entry(1);
+        exact.spa = Some("grande".to_string());
+        let mut typo = entry(2);
+        typo.spa = Some("grandi".to_string());
+
+        let words = vec!["grande".to_string()];
+        let langs = [LanguageCode::Spa];
+
+        let exact_score = score_entry(&exact, &words, &langs).unwrap();
+        let typo_score = score_entry(&typo, &words, &langs).unwrap();
+        assert!(exact_score > typo_score);
+    }
+
+    #[test]
+    fn score_entry_rewards_essential_flag() {
+        let mut plain = entry(1);
+        plain.spa = Some("casa".to_string());
+        let mut essential = entry(2);
+        essential.spa = Some("casa".to_string());
+        essential.essential_flag = true;
+
+        let words = vec!["casa".to_string()];
+        let langs = [LanguageCode::Spa];
+
+        let plain_score = score_entry(&plain, &words, &langs).unwrap();
+        let essential_score = score_entry(&essential, &words, &langs).unwrap();
+        assert!(essential_score > plain_score);
+    }
+
+    #[test]
+    fn score_entry_returns_none_when_nothing_matches() {
+        let mut e = entry(1);
+        e.spa = Some("casa".to_string());
+        let words = vec!["xyz".to_string()];
+        let langs = [LanguageCode::Spa];
+        assert_eq!(score_entry(&e, &words, &langs), None);
+    }
+
+    #[test]
+    fn score_entry_proximity_is_word_index_within_field_not_language_order() {
+        let mut near = entry(1);
+        near.spa = Some("casa grande".to_string());
+        let mut far = entry(2);
+        far.spa = Some("grande casa".to_string());
+
+        let words = vec!["casa".to_string()];
+        let langs = [LanguageCode::Spa];
+
+        let near_score = score_entry(&near, &words, &langs).unwrap();
+        let far_score = score_entry(&far, &words, &langs).unwrap();
+        assert!(near_score > far_score);
+    }
+
+    #[test]
+    fn normalize_keyword_score_is_monotonic_and_never_clamps() {
+        let low = normalize_keyword_score(1.0);
+        let mid = normalize_keyword_score(8.0);
+        let high = normalize_keyword_score(9.3);
+        assert!(low < mid);
+        assert!(mid < high);
+        assert!(high < 1.0);
+    }
+
+    #[test]
+    fn normalize_produces_unit_vectors() {
+        let v = normalize(vec![3.0, 4.0]);
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_similarity_of_orthogonal_vectors_is_zero() {
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn cosine_similarity_of_identical_unit_vectors_is_one() {
+        let v = normalize(vec![1.0, 2.0, 3.0]);
+        let similarity = cosine_similarity(&v, &v);
+        assert!((similarity - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn decay_halves_count_after_one_half_life() {
+        let analytics = SearchAnalytics::new(chrono::Duration::seconds(100));
+        let start = Utc::now();
+        let later = start + chrono::Duration::seconds(100);
+        let decayed = analytics.decay(10.0, start, later);
+        assert!((decayed - 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn record_changes_classifies_added_modified_and_removed() {
+        let mut dict = test_dictionary();
+
+        let mut fresh = HashMap::new();
+        let mut e1 = entry(1);
+        e1.spa = Some("casa".to_string());
+        fresh.insert(1, e1.clone());
+        let glosses = compute_glosses(&fresh);
+        dict.record_changes(&fresh, &glosses);
+
+        let added: Vec<_> = dict
+            .changes_since(Utc::now() - chrono::Duration::seconds(1))
+            .into_iter()
+            .filter(|c| c.id == 1)
+            .collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].kind, ChangeKind::Added);
+
+        let since_added = Utc::now();
+        let mut e1_modified = e1.clone();
+        e1_modified.spa = Some("caseta".to_string());
+        fresh.insert(1, e1_modified);
+        let glosses = compute_glosses(&fresh);
+        dict.record_changes(&fresh, &glosses);
+
+        let modified: Vec<_> = dict
+            .changes_since(since_added)
+            .into_iter()
+            .filter(|c| c.id == 1)
+            .collect();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].kind, ChangeKind::Modified);
+
+        let since_modified = Utc::now();
+        fresh.remove(&1);
+        let glosses = compute_glosses(&fresh);
+        dict.record_changes(&fresh, &glosses);
+
+        let removed: Vec<_> = dict
+            .changes_since(since_modified)
+            .into_iter()
+            .filter(|c| c.id == 1)
+            .collect();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].kind, ChangeKind::Removed);
+        assert!(dict.last_modified(1).is_none());
+    }
+}